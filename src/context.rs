@@ -1,7 +1,9 @@
 mod gpu;
 mod gui;
+mod texture;
 mod window;
 
 pub use gpu::{GpuContext, GpuContextError};
 pub use gui::GuiContext;
+pub use texture::TextureTarget;
 pub use window::{WindowContext, WindowContextError};
@@ -0,0 +1,118 @@
+use wgpu::{Device, Extent3d, TextureFormat};
+
+/// An offscreen render target backed by a texture and a readback buffer.
+///
+/// Used to render a view frame off-screen and copy it back to the CPU for
+/// export. Rows in the readback buffer are padded to the 256-byte
+/// `bytes_per_row` alignment required by `copy_texture_to_buffer`.
+pub struct TextureTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    padded_bytes_per_row: u32,
+}
+
+impl TextureTarget {
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("capture target"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture readback"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            texture,
+            view,
+            buffer,
+            width,
+            height,
+            format,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Record a copy of the rendered texture into the readback buffer.
+    pub fn copy_to_buffer(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Map the readback buffer and convert its texels to an RGBA image.
+    ///
+    /// The copy recorded by [`copy_to_buffer`](Self::copy_to_buffer) must have
+    /// been submitted before calling this.
+    pub fn read_rgba(&self, device: &Device) -> image::RgbaImage {
+        let slice = self.buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        let _ = device.poll(wgpu::PollType::Wait);
+
+        let data = slice.get_mapped_range();
+        let row_bytes = (self.width * 4) as usize;
+        let mut pixels = Vec::with_capacity(row_bytes * self.height as usize);
+        for row in 0..self.height as usize {
+            let start = row * self.padded_bytes_per_row as usize;
+            pixels.extend_from_slice(&data[start..start + row_bytes]);
+        }
+        drop(data);
+        self.buffer.unmap();
+
+        // The swapchain formats are BGRA; swizzle back to RGBA for `image`.
+        if matches!(
+            self.format,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        ) {
+            for texel in pixels.chunks_exact_mut(4) {
+                texel.swap(0, 2);
+            }
+        }
+
+        image::RgbaImage::from_raw(self.width, self.height, pixels)
+            .expect("readback buffer holds exactly width * height RGBA texels")
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use thiserror::Error;
-use wgpu::{Adapter, CreateSurfaceError, Device, Surface, SurfaceConfiguration};
+use wgpu::{Adapter, CreateSurfaceError, Device, PresentMode, Surface, SurfaceConfiguration};
 use winit::{dpi::PhysicalSize, error::OsError, window::Window};
 
 /// A renderable surface associated with a specific OS window.
@@ -9,6 +9,7 @@ pub struct WindowContext {
     pub window: Arc<Window>,
     pub surface: Surface<'static>,
     pub config: SurfaceConfiguration,
+    present_modes: Vec<PresentMode>,
 }
 
 #[derive(Error, Debug)]
@@ -42,7 +43,7 @@ impl WindowContext {
             format,
             width: size.width.max(1),
             height: size.height.max(1),
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: PresentMode::Fifo,
             alpha_mode: caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -54,6 +55,26 @@ impl WindowContext {
             window,
             surface,
             config,
+            present_modes: caps.present_modes,
+        }
+    }
+
+    /// Present modes supported by this surface.
+    pub fn present_modes(&self) -> &[PresentMode] {
+        &self.present_modes
+    }
+
+    /// Switch the present mode, falling back to `Fifo` when `mode` is unsupported.
+    pub fn set_present_mode(&mut self, device: &Device, mode: PresentMode) {
+        let mode = if self.present_modes.contains(&mode) {
+            mode
+        } else {
+            PresentMode::Fifo
+        };
+
+        if mode != self.config.present_mode {
+            self.config.present_mode = mode;
+            self.surface.configure(device, &self.config);
         }
     }
 
@@ -64,4 +85,9 @@ impl WindowContext {
             self.surface.configure(device, &self.config);
         }
     }
+
+    /// Re-apply the current configuration, recovering a lost or outdated surface.
+    pub fn reconfigure(&self, device: &Device) {
+        self.surface.configure(device, &self.config);
+    }
 }
@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+/// User-tweakable parameters shared between the control UI and the view shader.
+///
+/// The values are edited by the control window's widgets and uploaded to a
+/// uniform buffer each frame, so edits take effect on the next view draw.
+pub struct Params {
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub resolution: [f32; 2],
+
+    clock: Instant,
+    time: f32,
+}
+
+/// `std140`-compatible packing of [`Params`] for the view shader's uniform buffer.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ParamsUniform {
+    resolution: [f32; 2],
+    time: f32,
+    intensity: f32,
+    color: [f32; 3],
+    _pad: f32,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self {
+            color: [0.1, 0.4, 0.9],
+            intensity: 1.0,
+            resolution: [1.0, 1.0],
+            clock: Instant::now(),
+            time: 0.0,
+        }
+    }
+
+    /// Advance the monotonic `time` uniform from the frame clock.
+    pub fn tick(&mut self) {
+        self.time = self.clock.elapsed().as_secs_f32();
+    }
+
+    pub(crate) fn uniform(&self) -> ParamsUniform {
+        ParamsUniform {
+            resolution: self.resolution,
+            time: self.time,
+            intensity: self.intensity,
+            color: self.color,
+            _pad: 0.0,
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::new()
+    }
+}
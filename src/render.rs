@@ -5,17 +5,28 @@ mod control;
 mod view;
 
 pub use control::ControlRenderer;
-pub use view::ViewRenderer;
+pub use view::{Tonemap, ViewRenderer};
 
 use crate::context::{GpuContext, WindowContext};
 
 pub trait Renderer {
+    /// Record this renderer's draw commands for `target`'s frame into `encoder`.
+    fn encode(
+        &mut self,
+        gpu: &GpuContext,
+        target: &WindowContext,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    );
+
     fn render(&mut self, gpu: &GpuContext, target: &WindowContext) -> Result<(), RenderError> {
-        let frame = target.surface.get_current_texture()?;
+        let Some(frame) = acquire_frame(gpu, target)? else {
+            return Ok(());
+        };
         let view = frame.texture.create_view(&Default::default());
         let mut encoder = gpu.device.create_command_encoder(&Default::default());
 
-        todo!();
+        self.encode(gpu, target, &mut encoder, &view);
 
         gpu.queue.submit(Some(encoder.finish()));
         frame.present();
@@ -25,8 +36,36 @@ pub trait Renderer {
     }
 }
 
+/// Acquire the next swapchain frame, recovering transient surface losses.
+///
+/// Returns `Ok(None)` when the frame was skipped and a redraw re-requested
+/// (the surface was `Lost`/`Outdated` and has been reconfigured, or timed out).
+/// `OutOfMemory` is reported as the fatal [`RenderError::OutOfMemory`].
+pub(crate) fn acquire_frame(
+    gpu: &GpuContext,
+    target: &WindowContext,
+) -> Result<Option<wgpu::SurfaceTexture>, RenderError> {
+    match target.surface.get_current_texture() {
+        Ok(frame) => Ok(Some(frame)),
+        Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+            target.reconfigure(&gpu.device);
+            target.window.request_redraw();
+            Ok(None)
+        }
+        Err(SurfaceError::Timeout) => {
+            target.window.request_redraw();
+            Ok(None)
+        }
+        Err(SurfaceError::OutOfMemory) => Err(RenderError::OutOfMemory),
+        Err(error) => Err(RenderError::GetFrame(error)),
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RenderError {
     #[error("Failed to request next texture: {0}")]
     GetFrame(#[from] SurfaceError),
+
+    #[error("Surface ran out of memory")]
+    OutOfMemory,
 }
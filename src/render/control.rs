@@ -0,0 +1,97 @@
+use egui_wgpu::ScreenDescriptor;
+
+use super::{RenderError, acquire_frame};
+use crate::context::{GpuContext, GuiContext, WindowContext};
+
+/// Renders the control window's egui interface onto its surface.
+pub struct ControlRenderer;
+
+impl ControlRenderer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Run one egui frame and paint it onto the control surface.
+    ///
+    /// `run_ui` builds the interface; it runs inside the egui pass and may
+    /// mutate caller state in response to the widgets it draws.
+    pub fn render(
+        &mut self,
+        gpu: &GpuContext,
+        target: &WindowContext,
+        gui: &mut GuiContext,
+        run_ui: impl FnOnce(&egui::Context),
+    ) -> Result<(), RenderError> {
+        let Some(frame) = acquire_frame(gpu, target)? else {
+            return Ok(());
+        };
+        let view = frame.texture.create_view(&Default::default());
+        let mut encoder = gpu.device.create_command_encoder(&Default::default());
+
+        let input = gui.state.take_egui_input(&target.window);
+        let output = gui.context.run(input, run_ui);
+
+        gui.state
+            .handle_platform_output(&target.window, output.platform_output);
+
+        let primitives = gui
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: [target.config.width, target.config.height],
+            pixels_per_point: target.window.scale_factor() as f32,
+        };
+
+        for (id, delta) in &output.textures_delta.set {
+            gui.renderer
+                .update_texture(&gpu.device, &gpu.queue, *id, delta);
+        }
+
+        gui.renderer.update_buffers(
+            &gpu.device,
+            &gpu.queue,
+            &mut encoder,
+            &primitives,
+            &screen_descriptor,
+        );
+
+        {
+            let mut pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("egui"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                })
+                .forget_lifetime();
+
+            gui.renderer
+                .render(&mut pass, &primitives, &screen_descriptor);
+        }
+
+        gpu.queue.submit(Some(encoder.finish()));
+        frame.present();
+
+        for id in &output.textures_delta.free {
+            gui.renderer.free_texture(id);
+        }
+
+        target.window.request_redraw();
+        Ok(())
+    }
+}
+
+impl Default for ControlRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
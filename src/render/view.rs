@@ -0,0 +1,371 @@
+use wgpu::util::DeviceExt;
+
+use super::Renderer;
+use crate::context::{GpuContext, WindowContext};
+use crate::params::Params;
+
+const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Tonemapping operator applied when resolving the HDR target to the surface.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tonemap {
+    Reinhard,
+    Aces,
+}
+
+impl Tonemap {
+    fn index(self) -> u32 {
+        match self {
+            Tonemap::Reinhard => 0,
+            Tonemap::Aces => 1,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TonemapUniform {
+    exposure: f32,
+    operator: u32,
+    apply_srgb: u32,
+    _pad: u32,
+}
+
+/// Renders the main visual output into an HDR target, then tonemaps to the surface.
+pub struct ViewRenderer {
+    pub exposure: f32,
+    pub tonemap: Tonemap,
+
+    hdr_texture: wgpu::Texture,
+    hdr_view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    uniform: wgpu::Buffer,
+    apply_srgb: bool,
+
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+
+    params_buffer: wgpu::Buffer,
+    params_bind_group: wgpu::BindGroup,
+    scene_pipeline: wgpu::RenderPipeline,
+}
+
+impl ViewRenderer {
+    pub fn new(gpu: &GpuContext, target: &WindowContext) -> Self {
+        let device = &gpu.device;
+
+        let (hdr_texture, hdr_view) =
+            Self::create_hdr(device, target.config.width, target.config.height);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("view hdr sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let apply_srgb = !target.config.format.is_srgb();
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("view tonemap uniform"),
+            contents: bytemuck::bytes_of(&TonemapUniform {
+                exposure: 1.0,
+                operator: Tonemap::Aces.index(),
+                apply_srgb: apply_srgb as u32,
+                _pad: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("view tonemap bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &hdr_view, &sampler, &uniform);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("tonemap.wgsl"));
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("view tonemap pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("view tonemap pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(target.config.format.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("view params uniform"),
+            size: std::mem::size_of::<crate::params::ParamsUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let params_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("view params bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let params_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("view params bind group"),
+            layout: &params_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let scene_shader = device.create_shader_module(wgpu::include_wgsl!("scene.wgsl"));
+
+        let scene_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("view scene pipeline layout"),
+            bind_group_layouts: &[&params_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("view scene pipeline"),
+            layout: Some(&scene_layout),
+            vertex: wgpu::VertexState {
+                module: &scene_shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &scene_shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(HDR_FORMAT.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            exposure: 1.0,
+            tonemap: Tonemap::Aces,
+            hdr_texture,
+            hdr_view,
+            sampler,
+            uniform,
+            apply_srgb,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            params_buffer,
+            params_bind_group,
+            scene_pipeline,
+        }
+    }
+
+    /// Upload the current parameter values to the view shader's uniform buffer.
+    pub fn update_params(&self, queue: &wgpu::Queue, params: &Params) {
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params.uniform()));
+    }
+
+    /// Recreate the HDR target after the surface has been resized.
+    pub fn resize(&mut self, gpu: &GpuContext, target: &WindowContext) {
+        let (hdr_texture, hdr_view) =
+            Self::create_hdr(&gpu.device, target.config.width, target.config.height);
+
+        self.bind_group = Self::create_bind_group(
+            &gpu.device,
+            &self.bind_group_layout,
+            &hdr_view,
+            &self.sampler,
+            &self.uniform,
+        );
+        self.hdr_texture = hdr_texture;
+        self.hdr_view = hdr_view;
+    }
+
+    fn create_hdr(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("view hdr target"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        (texture, view)
+    }
+
+    /// Render the scene into the HDR target and tonemap it onto `view`.
+    ///
+    /// `view` may be a window surface view or an offscreen
+    /// [`TextureTarget`](crate::context::TextureTarget) view.
+    pub fn encode_into(
+        &mut self,
+        gpu: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        gpu.queue.write_buffer(
+            &self.uniform,
+            0,
+            bytemuck::bytes_of(&TonemapUniform {
+                exposure: self.exposure,
+                operator: self.tonemap.index(),
+                apply_srgb: self.apply_srgb as u32,
+                _pad: 0,
+            }),
+        );
+
+        // Scene pass: render the visual output into the HDR target.
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("view scene"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.hdr_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            pass.set_pipeline(&self.scene_pipeline);
+            pass.set_bind_group(0, &self.params_bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        // Tonemap pass: resolve the HDR target onto the destination view.
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("view tonemap"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        hdr_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        uniform: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("view tonemap bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(hdr_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
+
+impl Renderer for ViewRenderer {
+    fn encode(
+        &mut self,
+        gpu: &GpuContext,
+        _target: &WindowContext,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        self.encode_into(gpu, encoder, view);
+    }
+}
@@ -1,22 +1,20 @@
-use egui::Context;
-use egui_wgpu::{Renderer, RendererOptions};
-use egui_winit::State;
-use pollster::FutureExt;
 use std::sync::Arc;
+
 use thiserror::Error;
-use tracing::{debug, error};
-use wgpu::{
-    Adapter, CreateSurfaceError, Device, Instance, InstanceDescriptor, Queue, RequestAdapterError,
-    RequestDeviceError, Surface, SurfaceConfiguration, TextureFormat,
-};
+use tracing::{debug, error, info};
+use wgpu::{Instance, InstanceDescriptor, PresentMode, Surface};
 use winit::{
-    dpi::PhysicalSize,
-    error::OsError,
     event::WindowEvent,
     event_loop::ActiveEventLoop,
     window::{Window, WindowId},
 };
 
+use crate::context::{
+    GpuContext, GpuContextError, GuiContext, TextureTarget, WindowContext, WindowContextError,
+};
+use crate::params::Params;
+use crate::render::{ControlRenderer, RenderError, Renderer, Tonemap, ViewRenderer};
+
 /// Main application state orchestrating the GPU and windows.
 pub struct Session {
     pub gpu: GpuContext,
@@ -29,6 +27,15 @@ pub struct Session {
 
     /// UI Logic attached strictly to the control_window.
     pub gui: GuiContext,
+
+    view_renderer: ViewRenderer,
+    control_renderer: ControlRenderer,
+
+    /// Live parameters shared with the view shader.
+    params: Params,
+
+    /// Number of frames exported so far, used to name capture files.
+    captures: u32,
 }
 
 #[derive(Debug)]
@@ -73,11 +80,18 @@ impl Session {
         let gui_format = control_context.config.format;
         let gui = GuiContext::new(&control_context.window, &gpu.device, gui_format);
 
+        let view_renderer = ViewRenderer::new(&gpu, &view_context);
+        let control_renderer = ControlRenderer::new();
+
         Ok(Self {
             gpu,
             view: view_context,
             control: control_context,
             gui,
+            view_renderer,
+            control_renderer,
+            params: Params::new(),
+            captures: 0,
         })
     }
 
@@ -100,6 +114,7 @@ impl Session {
             WindowEvent::Resized(new_size) => {
                 if window_id == self.view.window.id() {
                     self.view.resize(&self.gpu.device, new_size);
+                    self.view_renderer.resize(&self.gpu, &self.view);
                 } else if window_id == self.control.window.id() {
                     self.control.resize(&self.gpu.device, new_size);
                 }
@@ -108,180 +123,165 @@ impl Session {
             }
 
             WindowEvent::RedrawRequested => {
-                if window_id == self.view.window.id() {
-                    self.render_view();
+                let result = if window_id == self.view.window.id() {
+                    self.render_view()
                 } else if window_id == self.control.window.id() {
-                    self.render_control();
+                    self.render_control()
+                } else {
+                    Ok(())
+                };
+
+                match result {
+                    Ok(()) => Ok(None),
+                    // A device out of memory is unrecoverable: tear the session down.
+                    Err(RenderError::OutOfMemory) => {
+                        error!("Surface out of memory, exiting");
+                        Ok(Some(SessionAction::Exit))
+                    }
+                    Err(error) => {
+                        error!("Render error: {error}");
+                        Ok(None)
+                    }
                 }
-
-                Ok(None)
             }
 
             _ => Ok(None),
         }
     }
 
-    fn render_view(&mut self) -> () {
-        // View render logic...
-        self.view.window.request_redraw();
-    }
+    fn render_view(&mut self) -> Result<(), RenderError> {
+        self.params.resolution = [self.view.config.width as f32, self.view.config.height as f32];
+        self.params.tick();
+        self.view_renderer.update_params(&self.gpu.queue, &self.params);
 
-    fn render_control(&mut self) -> () {
-        // Gui render logic..
-        self.control.window.request_redraw();
-    }
+        let Self {
+            gpu,
+            view,
+            view_renderer,
+            ..
+        } = self;
 
-    fn create_window_and_surface(
-        event_loop: &ActiveEventLoop,
-        instance: &Instance,
-        title: &str,
-    ) -> Result<(Arc<Window>, Surface<'static>), WindowContextError> {
-        let attr = Window::default_attributes().with_title(title);
-        let window = Arc::new(event_loop.create_window(attr)?);
-        let surface = instance.create_surface(window.clone())?;
-        Ok((window, surface))
+        view_renderer.render(gpu, view)
     }
-}
 
-/// Shared GPU resources.
-pub struct GpuContext {
-    pub instance: Instance,
-    pub adapter: Adapter,
-    pub device: Device,
-    pub queue: Queue,
-}
+    fn render_control(&mut self) -> Result<(), RenderError> {
+        let mut save_frame = false;
+
+        {
+            let Self {
+                gpu,
+                view,
+                control,
+                gui,
+                control_renderer,
+                view_renderer,
+                params,
+                ..
+            } = self;
+
+            control_renderer.render(gpu, control, gui, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.heading("Qualia");
+
+                    ui.separator();
+                    ui.label("Parameters");
+                    ui.color_edit_button_rgb(&mut params.color);
+                    ui.add(egui::Slider::new(&mut params.intensity, 0.0..=8.0).text("Intensity"));
+
+                    ui.separator();
+                    ui.label("Tonemapping");
+                    ui.add(egui::Slider::new(&mut view_renderer.exposure, 0.0..=4.0).text("Exposure"));
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut view_renderer.tonemap, Tonemap::Reinhard, "Reinhard");
+                        ui.selectable_value(&mut view_renderer.tonemap, Tonemap::Aces, "ACES");
+                    });
+
+                    ui.separator();
+                    ui.label("View presentation");
+                    present_mode_picker(ui, &gpu.device, view);
+
+                    ui.separator();
+                    if ui.button("Save frame").clicked() {
+                        save_frame = true;
+                    }
+                });
+            })?;
+        }
 
-impl GpuContext {
-    fn try_new(instance: &Instance, compatible_surface: &Surface) -> Result<Self, GpuContextError> {
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(compatible_surface),
-                force_fallback_adapter: false,
-            })
-            .block_on()?;
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: Some("Qualia Device"),
-                memory_hints: wgpu::MemoryHints::Performance,
-                ..Default::default()
-            })
-            .block_on()?;
+        if save_frame {
+            self.save_frame();
+        }
 
-        Ok(Self {
-            instance: instance.clone(),
-            adapter,
-            device,
-            queue,
-        })
+        Ok(())
     }
-}
 
-#[derive(Error, Debug)]
-pub enum GpuContextError {
-    #[error("wgpu::Adapter request failed: {0}")]
-    RequestAdapter(#[from] RequestAdapterError),
-    #[error("wgpu::Device request failed: {0}")]
-    RequestDevice(#[from] RequestDeviceError),
-}
+    /// Render one view frame off-screen and return it as an RGBA image.
+    pub fn capture_view(&mut self) -> image::RgbaImage {
+        let target = TextureTarget::new(
+            &self.gpu.device,
+            self.view.config.width,
+            self.view.config.height,
+            self.view.config.format,
+        );
 
-/// A renderable surface associated with a specific OS window.
-pub struct WindowContext {
-    pub window: Arc<Window>,
-    pub surface: Surface<'static>,
-    pub config: SurfaceConfiguration,
-}
+        self.params.tick();
+        self.view_renderer.update_params(&self.gpu.queue, &self.params);
 
-impl WindowContext {
-    fn from_raw(
-        window: Arc<Window>,
-        surface: Surface<'static>,
-        adapter: &Adapter,
-        device: &Device,
-    ) -> Self {
-        let size = window.inner_size();
-        let caps = surface.get_capabilities(adapter);
-
-        let format = caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(caps.formats[0]);
-
-        let config = SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format,
-            width: size.width.max(1),
-            height: size.height.max(1),
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-
-        surface.configure(device, &config);
-
-        Self {
-            window,
-            surface,
-            config,
-        }
+        let mut encoder = self.gpu.device.create_command_encoder(&Default::default());
+        self.view_renderer
+            .encode_into(&self.gpu, &mut encoder, &target.view);
+        target.copy_to_buffer(&mut encoder);
+        self.gpu.queue.submit(Some(encoder.finish()));
+
+        target.read_rgba(&self.gpu.device)
     }
 
-    pub fn resize(&mut self, device: &Device, size: PhysicalSize<u32>) {
-        if size.width > 0 && size.height > 0 {
-            self.config.width = size.width;
-            self.config.height = size.height;
-            self.surface.configure(device, &self.config);
+    fn save_frame(&mut self) {
+        let image = self.capture_view();
+        let path = format!("qualia-frame-{:04}.png", self.captures);
+
+        match image.save(&path) {
+            Ok(()) => {
+                self.captures += 1;
+                info!("Saved frame to {path}");
+            }
+            Err(error) => error!("Failed to save frame: {error}"),
         }
     }
-}
 
-#[derive(Error, Debug)]
-pub enum WindowContextError {
-    #[error("can't create winit::Window: {0}")]
-    CreateWindow(#[from] OsError),
-    #[error("can't create wgpu::Surface: {0}")]
-    CreateSurface(#[from] CreateSurfaceError),
-}
-
-/// State required to render the GUI.
-pub struct GuiContext {
-    pub context: Context,
-    pub state: State,
-    pub renderer: Renderer,
+    fn create_window_and_surface(
+        event_loop: &ActiveEventLoop,
+        instance: &Instance,
+        title: &str,
+    ) -> Result<(Arc<Window>, Surface<'static>), WindowContextError> {
+        let attr = Window::default_attributes().with_title(title);
+        let window = Arc::new(event_loop.create_window(attr)?);
+        let surface = instance.create_surface(window.clone())?;
+        Ok((window, surface))
+    }
 }
 
-impl GuiContext {
-    pub fn new(window: &Window, device: &Device, output_format: TextureFormat) -> Self {
-        let context = Context::default();
-
-        let state = State::new(
-            context.clone(),
-            egui::ViewportId::ROOT,
-            window,
-            Some(window.scale_factor() as f32),
-            None,
-            None,
-        );
-
-        let renderer = Renderer::new(
-            device,
-            output_format,
-            RendererOptions {
-                msaa_samples: 1,
-                ..Default::default()
-            },
-        );
+/// Draw radio buttons letting the user pick the target window's present mode.
+///
+/// Modes unsupported by the surface are shown disabled.
+fn present_mode_picker(ui: &mut egui::Ui, device: &wgpu::Device, target: &mut WindowContext) {
+    const MODES: [(PresentMode, &str); 3] = [
+        (PresentMode::Fifo, "VSync"),
+        (PresentMode::Mailbox, "Mailbox"),
+        (PresentMode::Immediate, "Immediate"),
+    ];
+
+    for (mode, label) in MODES {
+        let supported = target.present_modes().contains(&mode);
+        let mut selected = target.config.present_mode == mode;
+
+        let response = ui.add_enabled(supported, egui::RadioButton::new(selected, label));
+        if response.clicked() {
+            selected = true;
+        }
 
-        Self {
-            context,
-            state,
-            renderer,
+        if selected && target.config.present_mode != mode {
+            target.set_present_mode(device, mode);
         }
     }
 }